@@ -1,8 +1,16 @@
 use crate::alp_binding::*;
 use crate::error::*;
-use crate::Bitplanes;
+use crate::{Bitplanes, GrayImages};
 use libloading::Library;
-use std::ffi::{OsStr, c_long};
+use strum_macros::FromRepr;
+use std::cell::Cell;
+use std::ffi::{OsStr, c_long, c_ulong, c_void};
+use std::marker::PhantomData;
+use std::mem;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, Scope, ScopedJoinHandle};
+use std::time::Duration;
 
 
 
@@ -25,17 +33,50 @@ macro_rules! alp_call {
 type AlpDevAllocFn = unsafe extern fn(c_long, c_long, *mut ALP_ID) -> c_long;
 type AlpDevFreeFn = unsafe extern fn(ALP_ID) -> c_long;
 type AlpDevInquireFn = unsafe extern fn(ALP_ID, c_long, *mut c_long) -> c_long;
+type AlpDevControlFn = unsafe extern fn(ALP_ID, c_long, c_long) -> c_long;
+type AlpDevControlExFn = unsafe extern fn(ALP_ID, c_long, *mut tAlpDynSynchOutGate) -> c_long;
 type AlpSeqAllocFn = unsafe extern fn(ALP_ID, c_long, c_long, *mut ALP_ID) -> c_long;
 type AlpSeqFreeFn = unsafe extern fn(ALP_ID, ALP_ID) -> c_long;
 type AlpSeqPutFn = unsafe extern fn(ALP_ID, ALP_ID, c_long, c_long, *const u8) -> c_long;
+type AlpSeqPutExFn = unsafe extern fn(ALP_ID, ALP_ID, *mut tAlpLinePut, *const u8) -> c_long;
 type AlpProjStartContFn = unsafe extern fn(ALP_ID, ALP_ID) -> c_long;
 type AlpProjStartFn = unsafe extern fn(ALP_ID, ALP_ID) -> c_long;
 type AlpProjHaltFn = unsafe extern fn(ALP_ID) -> c_long;
 type AlpProjInquireExFn = unsafe extern fn(ALP_ID, c_long, *mut tAlpProjProgress) -> c_long;
 type AlpProjInquireFn = unsafe extern fn(ALP_ID, c_long, *mut c_long) -> c_long;
+type AlpProjControlFn = unsafe extern fn(ALP_ID, c_long, c_long) -> c_long;
+type AlpProjControlExFn = unsafe extern fn(ALP_ID, c_long, *mut c_void) -> c_long;
 type AlpProjWaitFn = unsafe extern fn(ALP_ID) -> c_long;
 type AlpSeqTimingFn = unsafe extern fn(ALP_ID, ALP_ID, c_long, c_long, c_long, c_long, c_long) -> c_long;
 type AlpSeqControlFn = unsafe extern fn(ALP_ID, ALP_ID, c_long, c_long) -> c_long;
+type AlpSeqInquireFn = unsafe extern fn(ALP_ID, ALP_ID, c_long, *mut c_long) -> c_long;
+type AlpLedAllocFn = unsafe extern fn(ALP_ID, c_long, *mut tAlpHldAllocParams, *mut ALP_ID) -> c_long;
+type AlpLedFreeFn = unsafe extern fn(ALP_ID, ALP_ID) -> c_long;
+type AlpLedControlFn = unsafe extern fn(ALP_ID, ALP_ID, c_long, c_long) -> c_long;
+type AlpLedInquireFn = unsafe extern fn(ALP_ID, ALP_ID, c_long, *mut c_long) -> c_long;
+type AlpLedInquireExFn = unsafe extern fn(ALP_ID, ALP_ID, c_long, *mut tAlpHldAllocParams) -> c_long;
+
+
+
+fn check_range(param: &'static str, value: usize, min: usize, max: usize)
+-> AlpResult<()> {
+    if value < min || value > max {
+        Err(AlpError::OutOfRange {
+            param,
+            value: value as i64,
+            min: min as i64,
+            max: max as i64
+        })
+    }
+    else { Ok(()) }
+}
+
+
+
+// Packs two 16-bit values into one control value like the Windows MAKELONG
+fn make_long(low: usize, high: usize) -> c_long {
+    ((high & 0xffff) << 16 | low & 0xffff) as c_ulong as c_long
+}
 
 
 
@@ -61,6 +102,43 @@ impl Alp {
             id: ret_id
         })
     }
+
+    // Allocates the next free device, or None once every device is taken
+    fn allocate_next_device(&self) -> AlpResult<Option<AlpDevice>> {
+        match self.allocate_device(None) {
+            Ok(dev) => Ok(Some(dev)),
+            Err(AlpError::NotAvailable | AlpError::NotOnline) => Ok(None),
+            Err(e) => Err(e)
+        }
+    }
+
+    // Lists devices by allocating every free one in turn, so while it runs no
+    // other process can allocate them. Devices already allocated elsewhere,
+    // including by this process, can't be allocated again and are not listed.
+    pub fn enumerate(&self) -> AlpResult<Vec<AttachedDevice>> {
+        let mut devices = Vec::new();
+
+        while let Some(dev) = self.allocate_next_device()? {
+            devices.push(dev);
+        }
+
+        devices.iter()
+            .map(|dev| Ok(AttachedDevice {
+                serial: dev.serial()?,
+                dmd_type: dev.dmd_type()?
+            }))
+            .collect()
+    }
+
+    // AlpDevAlloc takes the serial number as its device number
+    pub fn open_by_serial(&self, serial: u64) -> AlpResult<AlpDevice> {
+        match self.allocate_device(Some(serial)) {
+            Err(AlpError::NotAvailable | AlpError::NotOnline) => {
+                Err(AlpError::DeviceNotFound { serial })
+            },
+            res => res
+        }
+    }
 }
 
 
@@ -92,31 +170,197 @@ impl<'a> AlpDevice<'a> {
         Ok(AlpSequence {
             lib: &self.lib,
             dev: self,
-            id
+            id,
+            pictures: images as usize,
+            display_size: Cell::new(None),
+            data_format: Cell::new(ALP_DATA_MSB_ALIGN as c_long)
         })
     }
 
-    pub fn display_size(&self) -> AlpResult<(usize, usize)> {
-        let (mut width, mut height) = (0, 0);
+    pub fn allocate_gray_sequence(&self, bit_depth: usize, images: usize)
+    -> AlpResult<AlpSequence> {
+        check_range("bit_depth", bit_depth, 2, 8)?;
+        self.allocate_sequence(bit_depth, images)
+    }
+
+    pub fn allocate_led(&self, led_type: LedType) -> AlpResult<AlpLed> {
+        let mut id = 0;
 
         alp_call!(
-            self.lib, "AlpDevInquire", AlpDevInquireFn;
-            self.id, ALP_DEV_DISPLAY_WIDTH as c_long, &mut width
+            self.lib, "AlpLedAlloc", AlpLedAllocFn;
+            self.id, led_type as c_long, std::ptr::null_mut(), &mut id
         )?;
-        
+
+        Ok(AlpLed { dev: self, id, led_type })
+    }
+
+    fn inquire(&self, inquire_type: u32) -> AlpResult<c_long> {
+        let mut val = 0;
+
         alp_call!(
             self.lib, "AlpDevInquire", AlpDevInquireFn;
-            self.id, ALP_DEV_DISPLAY_HEIGHT as c_long, &mut height
+            self.id, inquire_type as c_long, &mut val
         )?;
 
+        Ok(val)
+    }
+
+    fn control(&self, control: DeviceControl, value: c_long) -> AlpResult<()> {
+        alp_call!(
+            self.lib, "AlpDevControl", AlpDevControlFn;
+            self.id, control as c_long, value
+        )
+    }
+
+    pub fn set_sync_polarity(&self, polarity: SyncPolarity) -> AlpResult<()> {
+        self.control(DeviceControl::SyncPolarity, polarity as c_long)
+    }
+
+    pub fn sync_polarity(&self) -> AlpResult<SyncPolarity> {
+        let val = self.inquire(ALP_SYNCH_POLARITY)?;
+
+        SyncPolarity::from_repr(val as i64).ok_or(AlpError::Unknown)
+    }
+
+    pub fn set_trigger_edge(&self, edge: TriggerEdge) -> AlpResult<()> {
+        self.control(DeviceControl::TriggerEdge, edge as c_long)
+    }
+
+    pub fn trigger_edge(&self) -> AlpResult<TriggerEdge> {
+        let val = self.inquire(ALP_TRIGGER_EDGE)?;
+
+        TriggerEdge::from_repr(val as i64).ok_or(AlpError::Unknown)
+    }
+
+    pub fn set_usb_disconnect_behaviour(&self, behaviour: UsbDisconnect)
+    -> AlpResult<()> {
+        self.control(DeviceControl::UsbDisconnectBehaviour, behaviour as c_long)
+    }
+
+    pub fn usb_disconnect_behaviour(&self) -> AlpResult<UsbDisconnect> {
+        let val = self.inquire(ALP_USB_DISCONNECT_BEHAVIOUR)?;
+
+        UsbDisconnect::from_repr(val as i64).ok_or(AlpError::Unknown)
+    }
+
+    pub fn set_gpio5_mux(&self, mux: Gpio5Mux) -> AlpResult<()> {
+        self.control(DeviceControl::Gpio5PinMux, mux as c_long)
+    }
+
+    pub fn gpio5_mux(&self) -> AlpResult<Gpio5Mux> {
+        let val = self.inquire(ALP_DEV_GPIO5_PIN_MUX)?;
+
+        Gpio5Mux::from_repr(val as i64).ok_or(AlpError::Unknown)
+    }
+
+    pub fn set_pwm_level(&self, percent: usize) -> AlpResult<()> {
+        check_range("pwm_level", percent, 0, 100)?;
+        self.control(DeviceControl::PwmLevel, percent as c_long)
+    }
+
+    pub fn pwm_level(&self) -> AlpResult<usize> {
+        Ok(self.inquire(ALP_PWM_LEVEL)? as usize)
+    }
+
+    pub fn set_dmd_mode(&self, mode: DmdMode) -> AlpResult<()> {
+        self.control(DeviceControl::DmdMode, mode as c_long)
+    }
+
+    pub fn dmd_mode(&self) -> AlpResult<DmdMode> {
+        let val = self.inquire(ALP_DEV_DMD_MODE)?;
+
+        DmdMode::from_repr(val as i64).ok_or(AlpError::Unknown)
+    }
+
+    pub fn park(&self) -> AlpResult<ParkGuard> {
+        self.set_dmd_mode(DmdMode::PowerFloat)?;
+
+        Ok(ParkGuard { dev: self })
+    }
+
+    pub fn set_sync_gate(&self, output: SyncOutput, gate: &SyncGate)
+    -> AlpResult<()> {
+        check_range("sync_gate_period", gate.period, 1, 16)?;
+
+        if let Some(slot) = gate.bad_slot {
+            check_range("sync_gate_slot", slot, 0, 15)?;
+        }
+
+        let mut raw = tAlpDynSynchOutGate {
+            Period: gate.period as u8,
+            Polarity: (gate.polarity == SyncPolarity::ActiveHigh) as u8,
+            Gate: gate.gate.map(|g| g as u8)
+        };
+
+        alp_call!(
+            self.lib, "AlpDevControlEx", AlpDevControlExFn;
+            self.id, output as c_long, &mut raw
+        )
+    }
+
+    pub fn set_sync_out_watchdog(&self, enabled: bool) -> AlpResult<()> {
+        let val = if enabled { ALP_ENABLE } else { ALP_DEFAULT };
+
+        self.control(DeviceControl::DynSyncOutWatchdog, val as c_long)
+    }
+
+    pub fn serial(&self) -> AlpResult<u64> {
+        Ok(self.inquire(ALP_DEVICE_NUMBER)? as u64)
+    }
+
+    pub fn dmd_type(&self) -> AlpResult<DmdType> {
+        let dmd_type = self.inquire(ALP_DEV_DMDTYPE)?;
+
+        let dmd_type = dmd_type as i64;
+
+        Ok(DmdType::from_repr(dmd_type).unwrap_or(DmdType::Other(dmd_type)))
+    }
+
+    pub fn display_size(&self) -> AlpResult<(usize, usize)> {
+        let dmd_type = self.dmd_type()?;
+
+        if dmd_type == DmdType::Disconnected {
+            return Err(AlpError::DmdDisconnected);
+        }
+
+        let width = self.inquire(ALP_DEV_DISPLAY_WIDTH)?;
+        let height = self.inquire(ALP_DEV_DISPLAY_HEIGHT)?;
+
         Ok((width as usize, height as usize))
     }
 
+    // Compares the size reported by the driver with the nominal size of the
+    // DMD type, for types whose geometry this crate knows
+    pub fn check_display_size(&self) -> AlpResult<DmdType> {
+        let dmd_type = self.dmd_type()?;
+        let reported = self.display_size()?;
+
+        match dmd_type.size() {
+            Some(nominal) if nominal != reported => {
+                Err(AlpError::DmdMismatch { dmd_type, nominal, reported })
+            },
+            _ => Ok(dmd_type)
+        }
+    }
+
+    pub fn info(&self) -> AlpResult<DeviceInfo> {
+        let state = self.inquire(ALP_DEV_STATE)?;
+
+        Ok(DeviceInfo {
+            serial: self.serial()?,
+            version: self.inquire(ALP_VERSION)? as u64,
+            state: DeviceState::from_repr(state as i64)
+                .ok_or(AlpError::Unknown)?,
+            available_memory: self.inquire(ALP_AVAIL_MEMORY)? as usize,
+            dmd_type: self.dmd_type()?
+        })
+    }
+
     pub fn halt(&self) -> AlpResult<()> {
         alp_call!(self.lib, "AlpProjHalt", AlpProjHaltFn; self.id)
     }
 
-    pub fn current_sequence_id(&self) -> AlpResult<Option<u64>> {
+    pub fn progress(&self) -> AlpResult<ProjectionProgress> {
         let mut progress = tAlpProjProgress {
             CurrentQueueId: 0,
             SequenceId: 0,
@@ -134,124 +378,1614 @@ impl<'a> AlpDevice<'a> {
             self.id, ALP_PROJ_PROGRESS as c_long, &mut progress
         )?;
 
-        let projecting = (progress.nFlags & ALP_FLAG_QUEUE_IDLE) == 0;
+        let flags = progress.nFlags;
+
+        Ok(ProjectionProgress {
+            current_queue_id: progress.CurrentQueueId as u64,
+            sequence_id: progress.SequenceId as u64,
+            waiting_sequences: progress.nWaitingSequences as usize,
+            sequence_counter: progress.nSequenceCounter as usize,
+            sequence_counter_underflow: progress.nSequenceCounterUnderflow as usize,
+            frame_counter: progress.nFrameCounter as usize,
+            picture_time: progress.nPictureTime as usize,
+            frames_per_sub_sequence: progress.nFramesPerSubSequence as usize,
+            queue_idle: (flags & ALP_FLAG_QUEUE_IDLE) != 0,
+            aborting: (flags & ALP_FLAG_SEQUENCE_ABORTING) != 0,
+            indefinite: (flags & ALP_FLAG_SEQUENCE_INDEFINITE) != 0,
+            frame_finished: (flags & ALP_FLAG_FRAME_FINISHED) != 0
+        })
+    }
+
+    pub fn current_sequence_id(&self) -> AlpResult<Option<u64>> {
+        let progress = self.progress()?;
 
-        Ok(projecting.then_some(progress.SequenceId as u64))
+        Ok((!progress.queue_idle).then_some(progress.sequence_id))
     }
 
-    pub fn is_projecting(&self) -> AlpResult<bool> {
+    fn proj_inquire(&self, inquire_type: u32) -> AlpResult<c_long> {
         let mut val = 0;
 
         alp_call!(
             self.lib, "AlpProjInquire", AlpProjInquireFn;
-            self.id, ALP_PROJ_STATE as c_long, &mut val
+            self.id, inquire_type as c_long, &mut val
         )?;
 
-        Ok(val == ALP_PROJ_ACTIVE as c_long)
+        Ok(val)
     }
 
-    pub fn wait(&self) -> AlpResult<()> {
-        alp_call!(self.lib, "AlpProjWait", AlpProjWaitFn; self.id)
+    fn proj_control(&self, control: ProjControl, value: c_long)
+    -> AlpResult<()> {
+        alp_call!(
+            self.lib, "AlpProjControl", AlpProjControlFn;
+            self.id, control as c_long, value
+        )
     }
-}
 
+    fn proj_control_ex<T>(&self, control: ProjControl, data: &mut T)
+    -> AlpResult<()> {
+        alp_call!(
+            self.lib, "AlpProjControlEx", AlpProjControlExFn;
+            self.id, control as c_long, data as *mut T as *mut c_void
+        )
+    }
 
+    pub fn write_bitplane_lut(&self, offset: usize, lut: &BitplaneLut)
+    -> AlpResult<()> {
+        let max_entries = self.proj_inquire(ALP_BPLUT_MAX_ENTRIES)? as usize;
 
-pub struct AlpSequence<'a> {
-    lib: &'a Library,
-    dev: &'a AlpDevice<'a>,
-    id: ALP_ID
-}
+        check_range("bplut_entries", offset+lut.len(), 0, max_entries)?;
 
-impl<'a> Drop for AlpSequence<'a> {
-    fn drop(&mut self) {
-        let current_seq = self.dev.current_sequence_id()
-            .expect("couldn't get current ALP sequence ID");
+        for &plane in &lut.planes {
+            check_range("bplut_plane", plane, 0, u16::MAX as usize)?;
+        }
 
-        if current_seq == Some(self.id as u64) {
-            self.dev.halt().expect("couldn't halt ALP device projection");
+        for (i, chunk) in lut.planes.chunks(2048).enumerate() {
+            let mut planes = [0; 2048];
+
+            for (dst, &plane) in planes.iter_mut().zip(chunk) {
+                *dst = plane as u16;
+            }
+
+            let mut write = tBplutWrite {
+                nOffset: (offset+i*2048) as c_long,
+                nSize: chunk.len() as c_long,
+                BitPlanes: planes
+            };
+
+            self.proj_control_ex(ProjControl::BplutWrite, &mut write)?;
         }
 
-        alp_call!(self.lib, "AlpSeqFree", AlpSeqFreeFn; self.dev.id, self.id)
-            .expect("couldn't free ALP sequence");
+        Ok(())
     }
-}
 
-impl<'a> AlpSequence<'a> {
-    pub fn id(&self) -> u64 {
-        self.id as u64
-    }
+    pub fn write_shear_table(&self, offset: usize, table: &ShearTable)
+    -> AlpResult<()> {
+        let (width, _) = self.display_size()?;
+        let width = width as isize;
+        let mut shifts = [0; 8192];
 
-    pub fn put_raw(&self, offset: usize, n: usize, data: &[u8]) -> AlpResult<()> {
-        let offset = offset as c_long;
-        let n = n as c_long;
+        check_range("shear_rows", offset+table.len(), 0, 8192)?;
 
-        alp_call!(
-            self.lib, "AlpSeqPut", AlpSeqPutFn;
-            self.dev.id, self.id, offset, n, data.as_ptr()
-        )
+        for (dst, &shift) in shifts.iter_mut().zip(&table.shifts) {
+            if shift < -width || shift > width {
+                return Err(AlpError::OutOfRange {
+                    param: "shear_shift",
+                    value: shift as i64,
+                    min: -width as i64,
+                    max: width as i64
+                });
+            }
+
+            *dst = shift as c_long;
+        }
+
+        let mut raw = tAlpShearTable {
+            nOffset: offset as c_long,
+            nSize: table.len() as c_long,
+            nShiftDistance: shifts
+        };
+
+        self.proj_control_ex(ProjControl::XShear, &mut raw)
     }
 
-    pub fn put_planes<D>(&self, offset: usize, planes: &Bitplanes<D>)
+    pub fn write_dmd_mask<D>(&self, mask: &Bitplanes<D>, blocks: MaskBlocks)
     -> AlpResult<()> where D: AsRef<[u8]> {
-        self.set_data_format(DataFormat::BinaryTopDown)?;
-        self.put_raw(offset, planes.planes(), planes)
+        let (width, height) = (mask.width(), mask.height());
+
+        if mask.planes() != 1 || self.display_size()? != (width, height) {
+            return Err(AlpError::ParameterInvalid);
+        }
+
+        let (rows, bitmap) = blocks.bitmap(mask);
+
+        match blocks {
+            MaskBlocks::Size16x16 => {
+                let mut raw = tAlpDmdMask {
+                    nRowOffset: 0,
+                    nRowCount: rows as c_long,
+                    Bitmap: [0; 2048]
+                };
+
+                check_range("dmd_mask_bytes", bitmap.len(), 0, 2048)?;
+                raw.Bitmap[..bitmap.len()].copy_from_slice(&bitmap);
+                self.proj_control_ex(ProjControl::DmdMaskWrite, &mut raw)
+            },
+            MaskBlocks::Size16x8 => {
+                let mut raw = tAlpDmdMask16K {
+                    nBlockWidth: 16,
+                    nRowOffset: 0,
+                    nRowCount: rows as c_long,
+                    Bitmap: [0; 16384]
+                };
+
+                check_range("dmd_mask_bytes", bitmap.len(), 0, 16384)?;
+                raw.Bitmap[..bitmap.len()].copy_from_slice(&bitmap);
+                self.proj_control_ex(ProjControl::DmdMaskWrite16K, &mut raw)
+            }
+        }
     }
 
-    pub fn start_cont(&self) -> AlpResult<()> {
-        alp_call!(
-            self.lib, "AlpProjStartCont", AlpProjStartContFn;
-            self.dev.id, self.id
-        )
+    pub fn is_projecting(&self) -> AlpResult<bool> {
+        Ok(self.proj_inquire(ALP_PROJ_STATE)? == ALP_PROJ_ACTIVE as c_long)
     }
-    
-    pub fn start(&self) -> AlpResult<()> {
-        alp_call!(
-            self.lib, "AlpProjStart", AlpProjStartFn;
-            self.dev.id, self.id
-        )
+
+    // In slave mode, the input edge is chosen with set_trigger_edge
+    pub fn set_projection_mode(&self, mode: ProjectionMode) -> AlpResult<()> {
+        self.proj_control(ProjControl::Mode, mode as c_long)
     }
 
-    pub fn set_cycles(&self, cycles: usize) -> AlpResult<()> {
-        self.set_control(Control::SeqRepeat, cycles as c_long)
+    pub fn projection_mode(&self) -> AlpResult<ProjectionMode> {
+        let val = self.proj_inquire(ALP_PROJ_MODE)?;
+
+        ProjectionMode::from_repr(val as i64).ok_or(AlpError::Unknown)
     }
 
-    pub fn set_picture_time(&self, time_us: usize) -> AlpResult<()> {
-        alp_call!(
-            self.lib, "AlpSeqTiming", AlpSeqTimingFn;
-            self.dev.id, self.id, ALP_DEFAULT as c_long, time_us as c_long,
-            ALP_DEFAULT as c_long, ALP_DEFAULT as c_long, ALP_DEFAULT as c_long
-        )
+    // Advance one frame per trigger edge, or free-run when None
+    pub fn set_step(&self, edge: Option<TriggerEdge>) -> AlpResult<()> {
+        let val = edge.map_or(ALP_DEFAULT as c_long, |e| e as c_long);
+
+        self.proj_control(ProjControl::Step, val)
     }
 
-    fn set_control(&self, control: Control, value: c_long) -> AlpResult<()> {
-        alp_call!(
-            self.lib, "AlpSeqControl", AlpSeqControlFn;
-            self.dev.id, self.id, control as c_long, value
-        )
+    pub fn set_wait_until(&self, wait: WaitUntil) -> AlpResult<()> {
+        self.proj_control(ProjControl::WaitUntil, wait as c_long)
     }
 
-    pub fn set_data_format(&self, format: DataFormat) -> AlpResult<()> {
-        self.set_control(Control::DataFormat, format as c_long)
+    pub fn set_async_abort(&self, enabled: bool) -> AlpResult<()> {
+        let val = if enabled { ALP_ENABLE } else { ALP_DEFAULT };
+
+        self.proj_control(ProjControl::AbortAsync, val as c_long)
+    }
+
+    pub fn abort(&self, point: AbortPoint) -> AlpResult<()> {
+        let control = match point {
+            AbortPoint::Sequence => ProjControl::AbortSequence,
+            AbortPoint::Frame => ProjControl::AbortFrame
+        };
+
+        self.proj_control(control, ALP_DEFAULT as c_long)
+    }
+
+    pub fn queue<'s>(&'s self) -> AlpResult<AlpQueue<'s, 's>> {
+        self.proj_control(
+            ProjControl::QueueMode,
+            ALP_PROJ_SEQUENCE_QUEUE as c_long
+        )?;
+
+        Ok(AlpQueue { dev: self, seqs: PhantomData })
+    }
+
+    pub fn set_projection_transform(&self, transform: &ProjectionTransform)
+    -> AlpResult<()> {
+        let t = transform;
+        let flag = |on: bool| {
+            (if on { ALP_ENABLE } else { ALP_DEFAULT }) as c_long
+        };
+
+        self.proj_control(ProjControl::XOffset, t.x_offset as c_long)?;
+        self.proj_control(ProjControl::YOffset, t.y_offset as c_long)?;
+        self.proj_control(ProjControl::Inversion, flag(t.invert))?;
+        self.proj_control(ProjControl::UpsideDown, flag(t.upside_down))?;
+        self.proj_control(ProjControl::LeftRightFlip, flag(t.left_right_flip))
+    }
+
+    pub fn write_frame_lut(&self, offset: usize, lut: &FrameLut)
+    -> AlpResult<()> {
+        let max_entries9 = self.proj_inquire(ALP_FLUT_MAX_ENTRIES9)? as usize;
+        let scale = lut.mode.entry_size9();
+        let max_frame = (1 << (9*scale))-1;
+        let control = match lut.mode {
+            FlutMode::Bits9 => ProjControl::FlutWrite9Bit,
+            FlutMode::Bits18 => ProjControl::FlutWrite18Bit
+        };
+
+        check_range("flut_entries", (offset+lut.len())*scale, 0, max_entries9)?;
+
+        for &frame in &lut.frames {
+            check_range("flut_frame", frame, 0, max_frame)?;
+        }
+
+        for (i, chunk) in lut.frames.chunks(4096).enumerate() {
+            let mut frames = [0; 4096];
+
+            for (dst, &frame) in frames.iter_mut().zip(chunk) {
+                *dst = frame as c_ulong;
+            }
+
+            let mut write = tFlutWrite {
+                nOffset: (offset+i*4096) as c_long,
+                nSize: chunk.len() as c_long,
+                FrameNumbers: frames
+            };
+
+            self.proj_control_ex(control, &mut write)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn wait(&self) -> AlpResult<()> {
+        alp_call!(self.lib, "AlpProjWait", AlpProjWaitFn; self.id)
+    }
+
+    fn inquire_temperature(&self, inquire_type: u32) -> AlpResult<f64> {
+        // Reported in units of 1/256 degrees Celsius
+        Ok(self.inquire(inquire_type)? as f64/256.0)
+    }
+
+    pub fn temperatures(&self) -> AlpResult<Temperatures> {
+        Ok(Temperatures {
+            ddc_fpga: self.inquire_temperature(ALP_DDC_FPGA_TEMPERATURE)?,
+            apps_fpga: self.inquire_temperature(ALP_APPS_FPGA_TEMPERATURE)?,
+            pcb: self.inquire_temperature(ALP_PCB_TEMPERATURE)?
+        })
+    }
+
+    pub fn max_temperatures(&self) -> AlpResult<Temperatures> {
+        Ok(Temperatures {
+            ddc_fpga: self.inquire_temperature(ALP_MAX_DDC_FPGA_TEMPERATURE)?,
+            apps_fpga: self.inquire_temperature(ALP_MAX_APPS_FPGA_TEMPERATURE)?,
+            pcb: self.inquire_temperature(ALP_MAX_PCB_TEMPERATURE)?
+        })
+    }
+
+    pub fn watch_temperatures<'scope>(
+        &'scope self,
+        scope: &'scope Scope<'scope, '_>,
+        limit: f64,
+        interval: Duration,
+        action: WatchdogAction
+    ) -> ThermalWatchdog<'scope> {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+
+        let trip = move || -> AlpResult<()> {
+            self.halt()?;
+
+            if action == WatchdogAction::PowerFloat {
+                self.set_dmd_mode(DmdMode::PowerFloat)?;
+            }
+
+            Ok(())
+        };
+
+        let handle = scope.spawn(move || {
+            while !thread_stop.load(Ordering::Relaxed) {
+                // A failed read is treated as overheating, since the
+                // temperature can no longer be trusted to be within limits
+                let temps = match self.temperatures() {
+                    Ok(temps) => temps,
+                    Err(e) => {
+                        let _ = trip();
+                        return Err(e);
+                    }
+                };
+
+                if temps.hottest() > limit {
+                    trip()?;
+                    return Ok(Some(temps));
+                }
+
+                thread::park_timeout(interval);
+            }
+
+            Ok(None)
+        });
+
+        ThermalWatchdog { stop, handle: Some(handle) }
     }
 }
 
 
 
-#[repr(i64)]
-#[derive(Copy, Clone, Debug, PartialEq)]
-pub enum DataFormat {
-    MsbAlign = ALP_DATA_MSB_ALIGN as i64,
-    LsbAlign = ALP_DATA_LSB_ALIGN as i64,
-    BinaryTopDown = ALP_DATA_BINARY_TOPDOWN as i64,
-    BinaryBottomUp = ALP_DATA_BINARY_BOTTOMUP as i64
+pub struct ParkGuard<'a> {
+    dev: &'a AlpDevice<'a>
 }
 
+impl<'a> Drop for ParkGuard<'a> {
+    fn drop(&mut self) {
+        self.dev.set_dmd_mode(DmdMode::Resume)
+            .expect("couldn't resume ALP DMD");
+    }
+}
 
 
-#[allow(dead_code)]
-#[repr(i64)]
-#[derive(Copy, Clone, Debug, PartialEq)]
+
+// Enqueued sequences must outlive the queue, so that none of them can be
+// freed while the device may still project it
+pub struct AlpQueue<'a, 's> {
+    dev: &'a AlpDevice<'a>,
+    seqs: PhantomData<Cell<&'s AlpSequence<'a>>>
+}
+
+impl<'a, 's> Drop for AlpQueue<'a, 's> {
+    fn drop(&mut self) {
+        self.shutdown().expect("couldn't disable ALP sequence queue");
+    }
+}
+
+impl<'a, 's> AlpQueue<'a, 's> {
+    fn check_device(&self, seq: &AlpSequence) -> AlpResult<()> {
+        if seq.dev.id == self.dev.id { Ok(()) }
+        else { Err(AlpError::ParameterInvalid) }
+    }
+
+    fn shutdown(&self) -> AlpResult<()> {
+        self.reset()?;
+        self.dev.halt()?;
+        self.dev.proj_control(ProjControl::QueueMode, ALP_PROJ_LEGACY as c_long)
+    }
+
+    pub fn enqueue(&self, seq: &'s AlpSequence<'a>) -> AlpResult<u64> {
+        self.check_device(seq)?;
+        seq.start()?;
+
+        Ok(self.dev.proj_inquire(ALP_PROJ_QUEUE_ID)? as u64)
+    }
+
+    pub fn enqueue_cont(&self, seq: &'s AlpSequence<'a>) -> AlpResult<u64> {
+        self.check_device(seq)?;
+        seq.start_cont()?;
+
+        Ok(self.dev.proj_inquire(ALP_PROJ_QUEUE_ID)? as u64)
+    }
+
+    pub fn available(&self) -> AlpResult<usize> {
+        Ok(self.dev.proj_inquire(ALP_PROJ_QUEUE_AVAIL)? as usize)
+    }
+
+    pub fn capacity(&self) -> AlpResult<usize> {
+        Ok(self.dev.proj_inquire(ALP_PROJ_QUEUE_MAX_AVAIL)? as usize)
+    }
+
+    pub fn reset(&self) -> AlpResult<()> {
+        self.dev.proj_control(ProjControl::ResetQueue, ALP_DEFAULT as c_long)
+    }
+
+    pub fn abort_sequence(&self) -> AlpResult<()> {
+        self.dev.abort(AbortPoint::Sequence)
+    }
+
+    pub fn abort_frame(&self) -> AlpResult<()> {
+        self.dev.abort(AbortPoint::Frame)
+    }
+
+    pub fn disable(self) -> AlpResult<()> {
+        let res = self.shutdown();
+
+        mem::forget(self);
+        res
+    }
+}
+
+
+
+pub struct ThermalWatchdog<'scope> {
+    stop: Arc<AtomicBool>,
+    handle: Option<ScopedJoinHandle<'scope, AlpResult<Option<Temperatures>>>>
+}
+
+impl<'scope> Drop for ThermalWatchdog<'scope> {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+
+        if let Some(handle) = &self.handle {
+            handle.thread().unpark();
+        }
+    }
+}
+
+impl<'scope> ThermalWatchdog<'scope> {
+    pub fn is_finished(&self) -> bool {
+        self.handle.as_ref().is_none_or(|h| h.is_finished())
+    }
+
+    pub fn stop(mut self) -> AlpResult<Option<Temperatures>> {
+        let handle = self.handle.take().unwrap();
+
+        self.stop.store(true, Ordering::Relaxed);
+        handle.thread().unpark();
+        handle.join().expect("ALP watchdog thread panicked")
+    }
+}
+
+
+
+// The picture count, display size and data format are kept here so that
+// repeated uploads to a running sequence don't inquire them every time
+pub struct AlpSequence<'a> {
+    lib: &'a Library,
+    dev: &'a AlpDevice<'a>,
+    id: ALP_ID,
+    pictures: usize,
+    display_size: Cell<Option<(usize, usize)>>,
+    data_format: Cell<c_long>
+}
+
+impl<'a> Drop for AlpSequence<'a> {
+    fn drop(&mut self) {
+        let current_seq = self.dev.current_sequence_id()
+            .expect("couldn't get current ALP sequence ID");
+
+        if current_seq == Some(self.id as u64) {
+            self.dev.halt().expect("couldn't halt ALP device projection");
+        }
+
+        alp_call!(self.lib, "AlpSeqFree", AlpSeqFreeFn; self.dev.id, self.id)
+            .expect("couldn't free ALP sequence");
+    }
+}
+
+impl<'a> AlpSequence<'a> {
+    pub fn id(&self) -> u64 {
+        self.id as u64
+    }
+
+    pub fn put_raw(&self, offset: usize, n: usize, data: &[u8]) -> AlpResult<()> {
+        let offset = offset as c_long;
+        let n = n as c_long;
+
+        alp_call!(
+            self.lib, "AlpSeqPut", AlpSeqPutFn;
+            self.dev.id, self.id, offset, n, data.as_ptr()
+        )
+    }
+
+    pub fn put_planes<D>(&self, offset: usize, planes: &Bitplanes<D>)
+    -> AlpResult<()> where D: AsRef<[u8]> {
+        self.set_data_format(DataFormat::BinaryTopDown)?;
+        self.put_raw(offset, planes.planes(), planes)
+    }
+
+    pub fn put_lines<D>(
+        &self,
+        pic_offset: usize,
+        pic_load: usize,
+        line_offset: usize,
+        line_load: usize,
+        planes: &Bitplanes<D>
+    ) -> AlpResult<()> where D: AsRef<[u8]> {
+        let (width, height) = self.display_size()?;
+        let pictures = self.pictures;
+
+        if planes.planes() != pic_load || planes.height() != line_load
+        || planes.width() != width {
+            return Err(AlpError::ParameterInvalid);
+        }
+
+        check_range("pic_load", pic_load, 1, pictures)?;
+        check_range("pic_offset", pic_offset, 0, pictures-pic_load)?;
+        check_range("line_load", line_load, 1, height)?;
+        check_range("line_offset", line_offset, 0, height-line_load)?;
+
+        if self.data_format.get() != DataFormat::BinaryTopDown as c_long {
+            self.set_data_format(DataFormat::BinaryTopDown)?;
+        }
+
+        let mut line_put = tAlpLinePut {
+            TransferMode: ALP_PUT_LINES as c_long,
+            PicOffset: pic_offset as c_long,
+            PicLoad: pic_load as c_long,
+            LineOffset: line_offset as c_long,
+            LineLoad: line_load as c_long
+        };
+
+        alp_call!(
+            self.lib, "AlpSeqPutEx", AlpSeqPutExFn;
+            self.dev.id, self.id, &mut line_put, planes.as_ptr()
+        )
+    }
+
+    pub fn put_gray<D>(
+        &self,
+        offset: usize,
+        images: &GrayImages<D>,
+        align: GrayAlign
+    ) -> AlpResult<()> where D: AsRef<[u8]> {
+        let bit_depth = self.inquire(ALP_BITPLANES)? as usize;
+        let pictures = self.inquire(ALP_PICNUM)? as usize;
+
+        if self.display_size()? != (images.width(), images.height()) {
+            return Err(AlpError::ParameterInvalid);
+        }
+
+        check_range("bit_depth", bit_depth, 2, 8)?;
+        check_range("frames", images.frames(), 1, pictures)?;
+        check_range("offset", offset, 0, pictures-images.frames())?;
+
+        self.set_control(Control::DataFormat, align as c_long)?;
+        self.put_raw(offset, images.frames(), images)
+    }
+
+    pub fn start_cont(&self) -> AlpResult<()> {
+        alp_call!(
+            self.lib, "AlpProjStartCont", AlpProjStartContFn;
+            self.dev.id, self.id
+        )
+    }
+    
+    pub fn start(&self) -> AlpResult<()> {
+        alp_call!(
+            self.lib, "AlpProjStart", AlpProjStartFn;
+            self.dev.id, self.id
+        )
+    }
+
+    pub fn set_cycles(&self, cycles: usize) -> AlpResult<()> {
+        self.set_control(Control::SeqRepeat, cycles as c_long)
+    }
+
+    // A single AlpSeqTiming call, left to the driver to check. set_timing
+    // checks the value first and reports an out-of-range one descriptively.
+    pub fn set_picture_time(&self, time_us: usize) -> AlpResult<()> {
+        self.apply_timing(&SequenceTiming::new().picture_time(time_us))
+    }
+
+    fn apply_timing(&self, timing: &SequenceTiming) -> AlpResult<()> {
+        let raw = |t: Option<usize>| t.unwrap_or(ALP_DEFAULT as usize) as c_long;
+
+        alp_call!(
+            self.lib, "AlpSeqTiming", AlpSeqTimingFn;
+            self.dev.id, self.id,
+            raw(timing.illuminate_time), raw(timing.picture_time),
+            raw(timing.sync_delay), raw(timing.sync_pulse_width),
+            raw(timing.trigger_in_delay)
+        )
+    }
+
+    fn current_timing(&self) -> AlpResult<SequenceTiming> {
+        let get = |inquire_type| -> AlpResult<Option<usize>> {
+            Ok(Some(self.inquire(inquire_type)? as usize))
+        };
+
+        Ok(SequenceTiming {
+            illuminate_time: get(ALP_ILLUMINATE_TIME)?,
+            picture_time: get(ALP_PICTURE_TIME)?,
+            sync_delay: get(ALP_SYNCH_DELAY)?,
+            sync_pulse_width: get(ALP_SYNCH_PULSEWIDTH)?,
+            trigger_in_delay: get(ALP_TRIGGER_IN_DELAY)?
+        })
+    }
+
+    fn check_delays(&self, timing: &SequenceTiming) -> AlpResult<()> {
+        if let Some(t) = timing.sync_delay {
+            let max = self.inquire(ALP_MAX_SYNCH_DELAY)? as usize;
+
+            check_range("sync_delay", t, 0, max)?;
+        }
+
+        if let Some(t) = timing.trigger_in_delay {
+            let max = self.inquire(ALP_MAX_TRIGGER_IN_DELAY)? as usize;
+
+            check_range("trigger_in_delay", t, 0, max)?;
+        }
+
+        Ok(())
+    }
+
+    // The delay maxima depend on the picture time and can only be inquired
+    // once it is applied. So when both are given, the new picture time is
+    // applied first and the previous timing is restored if anything fails.
+    pub fn set_timing(&self, timing: &SequenceTiming) -> AlpResult<()> {
+        let picture_time = match timing.picture_time {
+            Some(t) => {
+                let min = self.inquire(ALP_MIN_PICTURE_TIME)? as usize;
+                let max = self.inquire(ALP_MAX_PICTURE_TIME)? as usize;
+
+                check_range("picture_time", t, min, max)?;
+                t
+            },
+            None => self.inquire(ALP_PICTURE_TIME)? as usize
+        };
+
+        if let Some(t) = timing.illuminate_time {
+            let min = self.inquire(ALP_MIN_ILLUMINATE_TIME)? as usize;
+
+            check_range("illuminate_time", t, min, picture_time)?;
+        }
+
+        if let Some(t) = timing.sync_pulse_width {
+            check_range("sync_pulse_width", t, 0, picture_time)?;
+        }
+
+        let timing = SequenceTiming {
+            picture_time: Some(picture_time),
+            ..*timing
+        };
+
+        if timing.sync_delay.is_none() && timing.trigger_in_delay.is_none() {
+            return self.apply_timing(&timing);
+        }
+
+        let previous = self.current_timing()?;
+
+        if previous.picture_time == timing.picture_time {
+            self.check_delays(&timing)?;
+            return self.apply_timing(&timing);
+        }
+
+        self.apply_timing(&SequenceTiming::new().picture_time(picture_time))?;
+
+        let res = self.check_delays(&timing)
+            .and_then(|_| self.apply_timing(&timing));
+
+        if res.is_err() {
+            let _ = self.apply_timing(&previous);
+        }
+
+        res
+    }
+
+    fn set_control(&self, control: Control, value: c_long) -> AlpResult<()> {
+        alp_call!(
+            self.lib, "AlpSeqControl", AlpSeqControlFn;
+            self.dev.id, self.id, control as c_long, value
+        )?;
+
+        if control == Control::DataFormat { self.data_format.set(value); }
+
+        Ok(())
+    }
+
+    fn display_size(&self) -> AlpResult<(usize, usize)> {
+        match self.display_size.get() {
+            Some(size) => Ok(size),
+            None => {
+                let size = self.dev.display_size()?;
+
+                self.display_size.set(Some(size));
+                Ok(size)
+            }
+        }
+    }
+
+    pub fn set_data_format(&self, format: DataFormat) -> AlpResult<()> {
+        self.set_control(Control::DataFormat, format as c_long)
+    }
+
+    // Each of first/last frame and line is checked against the current value
+    // of the other, so moving a range past its current end means setting the
+    // last one first
+    pub fn set_first_frame(&self, frame: usize) -> AlpResult<()> {
+        let last = self.inquire(ALP_LASTFRAME)? as usize;
+
+        check_range("first_frame", frame, 0, last)?;
+        self.set_control(Control::FirstFrame, frame as c_long)
+    }
+
+    pub fn set_last_frame(&self, frame: usize) -> AlpResult<()> {
+        let pictures = self.inquire(ALP_PICNUM)? as usize;
+        let first = self.inquire(ALP_FIRSTFRAME)? as usize;
+
+        check_range("last_frame", frame, first, pictures-1)?;
+        self.set_control(Control::LastFrame, frame as c_long)
+    }
+
+    // A last line of ALP_DEFAULT means the bottom line of the DMD
+    fn last_line(&self, height: usize) -> AlpResult<usize> {
+        match self.inquire(ALP_LASTLINE)? {
+            last if last == ALP_DEFAULT as c_long => Ok(height-1),
+            last => Ok(last as usize)
+        }
+    }
+
+    pub fn set_first_line(&self, line: usize) -> AlpResult<()> {
+        let (_, height) = self.display_size()?;
+        let last = self.last_line(height)?;
+
+        check_range("first_line", line, 0, last)?;
+        self.set_control(Control::FirstLine, line as c_long)
+    }
+
+    pub fn set_last_line(&self, line: usize) -> AlpResult<()> {
+        let (_, height) = self.display_size()?;
+        let first = self.inquire(ALP_FIRSTLINE)? as usize;
+
+        check_range("last_line", line, first, height-1)?;
+        self.set_control(Control::LastLine, line as c_long)
+    }
+
+    pub fn set_line_inc(&self, lines: usize) -> AlpResult<()> {
+        let (_, height) = self.display_size()?;
+
+        check_range("line_inc", lines, 0, height)?;
+        self.set_control(Control::LineInc, lines as c_long)
+    }
+
+    // The scroll rows are sent as MAKELONG(line, frame) and the DMD area as
+    // MAKELONG(start row, row count)
+    pub fn set_scroll(&self, scroll: &ScrollConfig) -> AlpResult<()> {
+        let (_, height) = self.display_size()?;
+        let rows = height*self.inquire(ALP_PICNUM)? as usize;
+        let to_row = scroll.to_row;
+        let from_row = scroll.from_row;
+        let start_row = scroll.dmd_start_row;
+
+        check_range("scroll_to_row", to_row, 0, rows-1)?;
+        check_range("scroll_from_row", from_row, 0, to_row)?;
+        check_range("dmd_start_row", start_row, 0, height-1)?;
+        check_range("dmd_lines", scroll.dmd_lines, 1, height-start_row)?;
+
+        let from = make_long(from_row%height, from_row/height);
+        let to = make_long(to_row%height, to_row/height);
+        let lines = make_long(start_row, scroll.dmd_lines);
+
+        self.set_control(Control::ScrollFromRow, from)?;
+        self.set_control(Control::ScrollToRow, to)?;
+        self.set_control(Control::SeqLines, lines)?;
+        self.set_line_inc(scroll.line_inc)
+    }
+
+    // Writes the LUT to the device at the given offset and plays this sequence
+    // through it. The offset, in 9-bit entries, must be a multiple of 256.
+    pub fn set_frame_lut(&self, offset: usize, lut: &FrameLut)
+    -> AlpResult<()> {
+        let scale = lut.mode.entry_size9();
+
+        if lut.is_empty() || !(offset*scale).is_multiple_of(256) {
+            return Err(AlpError::ParameterInvalid);
+        }
+
+        for &frame in &lut.frames {
+            check_range("flut_frame", frame, 0, self.pictures-1)?;
+        }
+
+        self.dev.write_frame_lut(offset, lut)?;
+        self.set_control(Control::FlutMode, lut.mode as c_long)?;
+        self.set_control(Control::FlutEntries9, (lut.len()*scale) as c_long)?;
+        self.set_control(Control::FlutOffset9, (offset*scale) as c_long)
+    }
+
+    pub fn clear_frame_lut(&self) -> AlpResult<()> {
+        self.set_control(Control::FlutMode, ALP_FLUT_NONE as c_long)
+    }
+
+    // ALP_BITPLANE_LUT_MODE is the same control as ALP_PWM_MODE, so this is
+    // rejected while flex PWM is set. Set PwmMode::Default first to switch.
+    pub fn set_bitplane_lut(&self, mode: BitplaneLutMode, lut: &BitplaneLut)
+    -> AlpResult<()> {
+        let bit_depth = self.inquire(ALP_BITPLANES)? as usize;
+        let planes = bit_depth*self.inquire(ALP_PICNUM)? as usize;
+        let config = match mode {
+            BitplaneLutMode::Row => ALP_SEQ_CONFIG_BITPLANE_LUT_ROW,
+            _ => ALP_SEQ_CONFIG_DEFAULT
+        };
+
+        for &plane in &lut.planes {
+            check_range("bplut_plane", plane, 0, planes-1)?;
+        }
+
+        if self.inquire(ALP_PWM_MODE)? == ALP_FLEX_PWM as c_long {
+            return Err(AlpError::ParameterInvalid);
+        }
+
+        self.set_control(Control::SeqConfig, config as c_long)?;
+        self.set_control(Control::PwmMode, mode as c_long)?;
+
+        // The entry count only applies to a row LUT
+        if mode == BitplaneLutMode::Row {
+            self.set_control(Control::BitplaneLutEntries, lut.len() as c_long)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn set_dmd_mask(&self, blocks: Option<MaskBlocks>) -> AlpResult<()> {
+        let val = blocks.map_or(ALP_DEFAULT as c_long, |b| b as c_long);
+
+        self.set_control(Control::MaskSelect, val)
+    }
+
+    pub fn set_offset_select(&self, select: OffsetSelect) -> AlpResult<()> {
+        self.set_control(Control::XOffsetSelect, select as c_long)
+    }
+
+    pub fn set_x_shear(&self, enabled: bool) -> AlpResult<()> {
+        let val = if enabled { ALP_ENABLE } else { ALP_DEFAULT };
+
+        self.set_control(Control::XShearSelect, val as c_long)
+    }
+
+    pub fn set_dyn_sync_out(&self, period_us: usize, pulse_width_us: usize)
+    -> AlpResult<()> {
+        let picture_time = self.inquire(ALP_PICTURE_TIME)? as usize;
+
+        check_range("dyn_sync_out_period", period_us, 1, picture_time)?;
+        check_range("dyn_sync_out_pulse_width", pulse_width_us, 1, period_us-1)?;
+
+        self.set_control(Control::DynSyncOutPeriod, period_us as c_long)?;
+        self.set_control(
+            Control::DynSyncOutPulseWidth,
+            pulse_width_us as c_long
+        )
+    }
+
+    pub fn set_bit_num(&self, bits: usize) -> AlpResult<()> {
+        let bit_depth = self.inquire(ALP_BITPLANES)? as usize;
+
+        check_range("bit_num", bits, 1, bit_depth)?;
+        self.set_control(Control::BitNum, bits as c_long)
+    }
+
+    pub fn set_bin_mode(&self, mode: BinMode) -> AlpResult<()> {
+        self.set_control(Control::BinMode, mode as c_long)
+    }
+
+    // ALP_PWM_MODE is the same control as ALP_BITPLANE_LUT_MODE, so this is
+    // rejected while a frame or row bitplane LUT is set. Set the LUT mode back
+    // to BitplaneLutMode::Default first to switch.
+    pub fn set_pwm_mode(&self, mode: PwmMode) -> AlpResult<()> {
+        let current = self.inquire(ALP_PWM_MODE)?;
+        let lut_modes = [BitplaneLutMode::Frame, BitplaneLutMode::Row];
+
+        if lut_modes.iter().any(|&m| m as c_long == current) {
+            return Err(AlpError::ParameterInvalid);
+        }
+
+        self.set_control(Control::PwmMode, mode as c_long)
+    }
+
+    pub fn set_put_lock(&self, locked: bool) -> AlpResult<()> {
+        let val = if locked { ALP_ENABLE } else { ALP_DEFAULT };
+
+        self.set_control(Control::SeqPutLock, val as c_long)
+    }
+
+    fn inquire(&self, inquire_type: u32) -> AlpResult<c_long> {
+        let mut val = 0;
+
+        alp_call!(
+            self.lib, "AlpSeqInquire", AlpSeqInquireFn;
+            self.dev.id, self.id, inquire_type as c_long, &mut val
+        )?;
+
+        Ok(val)
+    }
+
+    pub fn info(&self) -> AlpResult<SequenceInfo> {
+        let data_format = self.inquire(ALP_DATA_FORMAT)?;
+
+        Ok(SequenceInfo {
+            bit_depth: self.inquire(ALP_BITPLANES)? as usize,
+            pictures: self.inquire(ALP_PICNUM)? as usize,
+            picture_time: self.inquire(ALP_PICTURE_TIME)? as usize,
+            illuminate_time: self.inquire(ALP_ILLUMINATE_TIME)? as usize,
+            min_picture_time: self.inquire(ALP_MIN_PICTURE_TIME)? as usize,
+            min_illuminate_time: self.inquire(ALP_MIN_ILLUMINATE_TIME)? as usize,
+            on_time: self.inquire(ALP_ON_TIME)? as usize,
+            off_time: self.inquire(ALP_OFF_TIME)? as usize,
+            sync_delay: self.inquire(ALP_SYNCH_DELAY)? as usize,
+            sync_pulse_width: self.inquire(ALP_SYNCH_PULSEWIDTH)? as usize,
+            data_format: DataFormat::from_repr(data_format as i64)
+                .ok_or(AlpError::Unknown)?
+        })
+    }
+}
+
+
+
+pub struct AlpLed<'dev> {
+    dev: &'dev AlpDevice<'dev>,
+    id: ALP_ID,
+    led_type: LedType
+}
+
+impl<'dev> Drop for AlpLed<'dev> {
+    fn drop(&mut self) {
+        // Free the LED even if switching it off failed
+        let off = self.set_force_off(LedForce::Off);
+        let free = alp_call!(
+            self.dev.lib, "AlpLedFree", AlpLedFreeFn;
+            self.dev.id, self.id
+        );
+
+        off.and(free).expect("couldn't switch off and free ALP LED");
+    }
+}
+
+impl<'dev> AlpLed<'dev> {
+    fn control(&self, control: LedControl, value: c_long) -> AlpResult<()> {
+        alp_call!(
+            self.dev.lib, "AlpLedControl", AlpLedControlFn;
+            self.dev.id, self.id, control as c_long, value
+        )
+    }
+
+    fn inquire(&self, inquire_type: u32) -> AlpResult<c_long> {
+        let mut val = 0;
+
+        alp_call!(
+            self.dev.lib, "AlpLedInquire", AlpLedInquireFn;
+            self.dev.id, self.id, inquire_type as c_long, &mut val
+        )?;
+
+        Ok(val)
+    }
+
+    pub fn led_type(&self) -> LedType {
+        self.led_type
+    }
+
+    pub fn set_current_ma(&self, current_ma: usize) -> AlpResult<()> {
+        self.led_type.check_current_ma(current_ma)?;
+        self.control(LedControl::SetCurrent, current_ma as c_long)
+    }
+
+    pub fn set_brightness(&self, percent: usize) -> AlpResult<()> {
+        check_range("led_brightness", percent, 0, 100)?;
+        self.control(LedControl::Brightness, percent as c_long)
+    }
+
+    pub fn set_force_off(&self, force: LedForce) -> AlpResult<()> {
+        self.control(LedControl::ForceOff, force as c_long)
+    }
+
+    pub fn measured_current_ma(&self) -> AlpResult<usize> {
+        Ok(self.inquire(ALP_LED_MEASURED_CURRENT)? as usize)
+    }
+
+    // Both in degrees Celsius, reported in units of 1/256 degrees
+    pub fn temperatures(&self) -> AlpResult<(f64, f64)> {
+        let reference = self.inquire(ALP_LED_TEMPERATURE_REF)?;
+        let junction = self.inquire(ALP_LED_TEMPERATURE_JUNCTION)?;
+
+        Ok((reference as f64/256.0, junction as f64/256.0))
+    }
+
+    // I2C addresses of the LED driver's DAC and ADC
+    pub fn i2c_addresses(&self) -> AlpResult<(u64, u64)> {
+        let mut params = tAlpHldAllocParams {
+            I2cDacAddr: 0,
+            I2cAdcAddr: 0
+        };
+
+        alp_call!(
+            self.dev.lib, "AlpLedInquireEx", AlpLedInquireExFn;
+            self.dev.id, self.id, ALP_LED_ALLOC_PARAMS as c_long, &mut params
+        )?;
+
+        Ok((params.I2cDacAddr as u64, params.I2cAdcAddr as u64))
+    }
+}
+
+
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AttachedDevice {
+    pub serial: u64,
+    pub dmd_type: DmdType
+}
+
+
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DeviceInfo {
+    pub serial: u64,
+    pub version: u64,
+    pub state: DeviceState,
+    // Free sequence memory, counted in binary pictures
+    pub available_memory: usize,
+    pub dmd_type: DmdType
+}
+
+
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Temperatures {
+    pub ddc_fpga: f64,
+    pub apps_fpga: f64,
+    pub pcb: f64
+}
+
+impl Temperatures {
+    pub fn hottest(&self) -> f64 {
+        self.ddc_fpga.max(self.apps_fpga).max(self.pcb)
+    }
+}
+
+
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum WatchdogAction {
+    Halt,
+    PowerFloat
+}
+
+
+
+#[repr(i64)]
+#[derive(Copy, Clone, Debug, PartialEq, FromRepr)]
+pub enum DeviceState {
+    Busy = ALP_DEV_BUSY as i64,
+    Ready = ALP_DEV_READY as i64,
+    Idle = ALP_DEV_IDLE as i64
+}
+
+
+
+#[repr(i64)]
+#[derive(Copy, Clone, Debug, PartialEq, FromRepr)]
+pub enum DmdType {
+    Xga = ALP_DMDTYPE_XGA as i64,
+    SxgaPlus = ALP_DMDTYPE_SXGA_PLUS as i64,
+    Hd1080p095A = ALP_DMDTYPE_1080P_095A as i64,
+    Xga07A = ALP_DMDTYPE_XGA_07A as i64,
+    Xga055A = ALP_DMDTYPE_XGA_055A as i64,
+    Xga055X = ALP_DMDTYPE_XGA_055X as i64,
+    Wuxga096A = ALP_DMDTYPE_WUXGA_096A as i64,
+    Wqxga400MHz090A = ALP_DMDTYPE_WQXGA_400MHZ_090A as i64,
+    Wqxga480MHz090A = ALP_DMDTYPE_WQXGA_480MHZ_090A as i64,
+    Hd1080p065A = ALP_DMDTYPE_1080P_065A as i64,
+    Hd1080p065S600 = ALP_DMDTYPE_1080P_065_S600 as i64,
+    WxgaS450 = ALP_DMDTYPE_WXGA_S450 as i64,
+    Dlpc910Rev = ALP_DMDTYPE_DLPC910REV as i64,
+    Disconnected = ALP_DMDTYPE_DISCONNECT as i64,
+    // A type code this crate doesn't know, whose geometry is never checked
+    #[strum(disabled)]
+    Other(i64)
+}
+
+impl DmdType {
+    // Nominal (width, height, mirror pitch in um, max binary frame rate in Hz)
+    // of the TI chip behind each type. These are only informative: the size
+    // the driver reports is always the one used for geometry checks.
+    fn specs(&self) -> Option<(usize, usize, f64, usize)> {
+        match self {
+            // DLP7000, 0.7" XGA
+            Self::Xga | Self::Xga07A => Some((1024, 768, 13.68, 22727)),
+            // DLP5500, 0.55" XGA
+            Self::Xga055A | Self::Xga055X => Some((1024, 768, 10.8, 5000)),
+            // 0.95" SXGA+
+            Self::SxgaPlus => Some((1400, 1050, 13.68, 16000)),
+            // DLP9500, 0.95" 1080p
+            Self::Hd1080p095A => Some((1920, 1080, 10.8, 17857)),
+            // 0.96" WUXGA
+            Self::Wuxga096A => Some((1920, 1200, 10.8, 16393)),
+            // DLP9000 and DLP9000X, 0.9" WQXGA
+            Self::Wqxga400MHz090A => Some((2560, 1600, 7.56, 12500)),
+            Self::Wqxga480MHz090A => Some((2560, 1600, 7.56, 14989)),
+            // DLP6500, 0.65" 1080p
+            Self::Hd1080p065A
+            | Self::Hd1080p065S600 => Some((1920, 1080, 7.56, 9523)),
+            // DLP650LNIR, 0.65" WXGA in the S450 package
+            Self::WxgaS450 => Some((1280, 800, 10.8, 12500)),
+            Self::Dlpc910Rev | Self::Disconnected | Self::Other(_) => None
+        }
+    }
+
+    pub fn size(&self) -> Option<(usize, usize)> {
+        self.specs().map(|(width, height, _, _)| (width, height))
+    }
+
+    pub fn mirror_pitch_um(&self) -> Option<f64> {
+        self.specs().map(|(_, _, pitch, _)| pitch)
+    }
+
+    pub fn max_binary_rate_hz(&self) -> Option<usize> {
+        self.specs().map(|(_, _, _, rate)| rate)
+    }
+}
+
+
+
+#[repr(i64)]
+#[derive(Copy, Clone, Debug, PartialEq, FromRepr)]
+pub enum SyncPolarity {
+    ActiveHigh = ALP_LEVEL_HIGH as i64,
+    ActiveLow = ALP_LEVEL_LOW as i64
+}
+
+
+
+#[repr(i64)]
+#[derive(Copy, Clone, Debug, PartialEq, FromRepr)]
+pub enum TriggerEdge {
+    Falling = ALP_EDGE_FALLING as i64,
+    Rising = ALP_EDGE_RISING as i64
+}
+
+
+
+#[repr(i64)]
+#[derive(Copy, Clone, Debug, PartialEq, FromRepr)]
+pub enum UsbDisconnect {
+    Ignore = ALP_USB_IGNORE as i64,
+    Reset = ALP_USB_RESET as i64
+}
+
+
+
+#[repr(i64)]
+#[derive(Copy, Clone, Debug, PartialEq, FromRepr)]
+pub enum Gpio5Mux {
+    StaticLow = ALP_GPIO_STATIC_LOW as i64,
+    StaticHigh = ALP_GPIO_STATIC_HIGH as i64,
+    DynSyncOutActiveLow = ALP_GPIO_DYN_SYNCH_OUT_ACTIVE_LOW as i64,
+    DynSyncOutActiveHigh = ALP_GPIO_DYN_SYNCH_OUT_ACTIVE_HIGH as i64
+}
+
+
+
+#[repr(i64)]
+#[derive(Copy, Clone, Debug, PartialEq, FromRepr)]
+pub enum DmdMode {
+    Resume = ALP_DMD_RESUME as i64,
+    PowerFloat = ALP_DMD_POWER_FLOAT as i64
+}
+
+
+
+#[repr(i64)]
+#[derive(Copy, Clone, Debug, PartialEq, FromRepr)]
+pub enum ProjectionMode {
+    Master = ALP_MASTER as i64,
+    Slave = ALP_SLAVE as i64
+}
+
+
+
+#[repr(i64)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum WaitUntil {
+    PictureTime = ALP_PROJ_WAIT_PIC_TIME as i64,
+    IlluminateTime = ALP_PROJ_WAIT_ILLU_TIME as i64
+}
+
+
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AbortPoint {
+    Sequence,
+    Frame
+}
+
+
+
+#[repr(i64)]
+#[derive(Copy, Clone, Debug, PartialEq, FromRepr)]
+pub enum LedType {
+    Pt120Red = ALP_HLD_PT120_RED as i64,
+    Pt120Rax = ALP_HLD_PT120_RAX as i64,
+    Pt120Green = ALP_HLD_PT120_GREEN as i64,
+    Pt120Blue = ALP_HLD_PT120_BLUE as i64,
+    Pt120TeBlue = ALP_HLD_PT120TE_BLUE as i64,
+    Cbt90Uv = ALP_HLD_CBT90_UV as i64,
+    Cbt120Uv = ALP_HLD_CBT120_UV as i64,
+    Cbm120Uv365 = ALP_HLD_CBM120_UV365 as i64,
+    Cbm120Uv = ALP_HLD_CBM120_UV as i64,
+    Cbm90x33Ird = ALP_HLD_CBM90X33_IRD as i64,
+    Cbm120Fr = ALP_HLD_CBM120_FR as i64,
+    Cbt90White = ALP_HLD_CBT90_WHITE as i64,
+    Cbt140White = ALP_HLD_CBT140_WHITE as i64,
+    CMulti405Gr = ALP_HLD_C_MULTI_405GR as i64,
+    CMultiRgb = ALP_HLD_C_MULTI_RGB as i64
+}
+
+impl LedType {
+    // Maximum LED currents per ALP_HLD_* type, from the LED type table under
+    // AlpLedAlloc in the ViALUX ALP-4.3 API description
+    pub fn max_current_ma(&self) -> usize {
+        match self {
+            // HLD PT120 modules: 21 A
+            Self::Pt120Red | Self::Pt120Rax | Self::Pt120Green
+            | Self::Pt120Blue | Self::Pt120TeBlue => 21000,
+            // HLD CBT90 modules: 13.5 A
+            Self::Cbt90Uv | Self::Cbt90White => 13500,
+            // HLD CBT120 and CBM120 modules: 18 A
+            Self::Cbt120Uv | Self::Cbm120Uv365 | Self::Cbm120Uv
+            | Self::Cbm120Fr => 18000,
+            // HLD CBT140 module: 28 A
+            Self::Cbt140White => 28000,
+            // HLD CBM90X33 module: 9 A
+            Self::Cbm90x33Ird => 9000,
+            // HLD C-MULTI modules, per channel: 10 A
+            Self::CMulti405Gr | Self::CMultiRgb => 10000
+        }
+    }
+
+    fn check_current_ma(&self, current_ma: usize) -> AlpResult<()> {
+        check_range("led_current_ma", current_ma, 0, self.max_current_ma())
+    }
+}
+
+
+
+#[repr(i64)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LedForce {
+    Auto = ALP_LED_AUTO_OFF as i64,
+    Off = ALP_LED_OFF as i64,
+    On = ALP_LED_ON as i64
+}
+
+
+
+#[repr(i64)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SyncOutput {
+    Out1 = ALP_DEV_DYN_SYNCH_OUT1_GATE as i64,
+    Out2 = ALP_DEV_DYN_SYNCH_OUT2_GATE as i64,
+    Out3 = ALP_DEV_DYN_SYNCH_OUT3_GATE as i64
+}
+
+
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SyncGate {
+    period: usize,
+    polarity: SyncPolarity,
+    gate: [bool; 16],
+    // First slot index given that was out of range, reported on use
+    bad_slot: Option<usize>
+}
+
+impl SyncGate {
+    pub fn new(period: usize) -> Self {
+        Self {
+            period,
+            polarity: SyncPolarity::ActiveHigh,
+            gate: [false; 16],
+            bad_slot: None
+        }
+    }
+
+    pub fn polarity(mut self, polarity: SyncPolarity) -> Self {
+        self.polarity = polarity;
+        self
+    }
+
+    pub fn slot(mut self, slot: usize, active: bool) -> Self {
+        match self.gate.get_mut(slot) {
+            Some(gate) => *gate = active,
+            None => { self.bad_slot.get_or_insert(slot); }
+        }
+
+        self
+    }
+
+    pub fn slots(mut self, gate: [bool; 16]) -> Self {
+        self.gate = gate;
+        self
+    }
+}
+
+
+
+// Rows count through the sequence's pictures stacked into one tall picture
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ScrollConfig {
+    pub from_row: usize,
+    pub to_row: usize,
+    pub dmd_start_row: usize,
+    pub dmd_lines: usize,
+    pub line_inc: usize
+}
+
+
+
+// Picture time is in microseconds
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ProjectionProgress {
+    pub current_queue_id: u64,
+    pub sequence_id: u64,
+    pub waiting_sequences: usize,
+    pub sequence_counter: usize,
+    pub sequence_counter_underflow: usize,
+    pub frame_counter: usize,
+    pub picture_time: usize,
+    pub frames_per_sub_sequence: usize,
+    pub queue_idle: bool,
+    pub aborting: bool,
+    pub indefinite: bool,
+    pub frame_finished: bool
+}
+
+
+
+// All times are in microseconds, and unset ones are left to the driver
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct SequenceTiming {
+    illuminate_time: Option<usize>,
+    picture_time: Option<usize>,
+    sync_delay: Option<usize>,
+    sync_pulse_width: Option<usize>,
+    trigger_in_delay: Option<usize>
+}
+
+impl SequenceTiming {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn illuminate_time(mut self, time_us: usize) -> Self {
+        self.illuminate_time = Some(time_us);
+        self
+    }
+
+    pub fn picture_time(mut self, time_us: usize) -> Self {
+        self.picture_time = Some(time_us);
+        self
+    }
+
+    pub fn sync_delay(mut self, time_us: usize) -> Self {
+        self.sync_delay = Some(time_us);
+        self
+    }
+
+    pub fn sync_pulse_width(mut self, time_us: usize) -> Self {
+        self.sync_pulse_width = Some(time_us);
+        self
+    }
+
+    pub fn trigger_in_delay(mut self, time_us: usize) -> Self {
+        self.trigger_in_delay = Some(time_us);
+        self
+    }
+}
+
+
+
+// All times are in microseconds
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SequenceInfo {
+    pub bit_depth: usize,
+    pub pictures: usize,
+    pub picture_time: usize,
+    pub illuminate_time: usize,
+    pub min_picture_time: usize,
+    pub min_illuminate_time: usize,
+    pub on_time: usize,
+    pub off_time: usize,
+    pub sync_delay: usize,
+    pub sync_pulse_width: usize,
+    pub data_format: DataFormat
+}
+
+
+
+#[repr(i64)]
+#[derive(Copy, Clone, Debug, PartialEq, FromRepr)]
+pub enum DataFormat {
+    MsbAlign = ALP_DATA_MSB_ALIGN as i64,
+    LsbAlign = ALP_DATA_LSB_ALIGN as i64,
+    BinaryTopDown = ALP_DATA_BINARY_TOPDOWN as i64,
+    BinaryBottomUp = ALP_DATA_BINARY_BOTTOMUP as i64
+}
+
+
+
+#[repr(i64)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GrayAlign {
+    Msb = ALP_DATA_MSB_ALIGN as i64,
+    Lsb = ALP_DATA_LSB_ALIGN as i64
+}
+
+
+
+#[repr(i64)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BinMode {
+    Normal = ALP_BIN_NORMAL as i64,
+    Uninterrupted = ALP_BIN_UNINTERRUPTED as i64
+}
+
+
+
+#[repr(i64)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PwmMode {
+    Default = ALP_DEFAULT as i64,
+    Flex = ALP_FLEX_PWM as i64
+}
+
+
+
+#[repr(i64)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FlutMode {
+    Bits9 = ALP_FLUT_9BIT as i64,
+    Bits18 = ALP_FLUT_18BIT as i64
+}
+
+impl FlutMode {
+    // Sequence FLUT offsets and entry counts are given in 9-bit units
+    fn entry_size9(&self) -> usize {
+        match self {
+            Self::Bits9 => 1,
+            Self::Bits18 => 2
+        }
+    }
+}
+
+
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrameLut {
+    mode: FlutMode,
+    frames: Vec<usize>
+}
+
+impl FrameLut {
+    pub fn new(mode: FlutMode, frames: Vec<usize>) -> Self {
+        Self { mode, frames }
+    }
+
+    pub fn mode(&self) -> FlutMode {
+        self.mode
+    }
+
+    pub fn frames(&self) -> &[usize] {
+        &self.frames
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+}
+
+
+
+#[repr(i64)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BitplaneLutMode {
+    Default = ALP_BITPLANE_LUT_DEFAULT as i64,
+    Frame = ALP_BITPLANE_LUT_FRAME as i64,
+    Row = ALP_BITPLANE_LUT_ROW as i64
+}
+
+
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BitplaneLut {
+    planes: Vec<usize>
+}
+
+impl BitplaneLut {
+    pub fn new(planes: Vec<usize>) -> Self {
+        Self { planes }
+    }
+
+    pub fn planes(&self) -> &[usize] {
+        &self.planes
+    }
+
+    pub fn len(&self) -> usize {
+        self.planes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.planes.is_empty()
+    }
+}
+
+
+
+#[repr(i64)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MaskBlocks {
+    Size16x16 = ALP_DMD_MASK_16X16 as i64,
+    Size16x8 = ALP_DMD_MASK_16X8 as i64
+}
+
+impl MaskBlocks {
+    fn block_height(&self) -> usize {
+        match self {
+            Self::Size16x16 => 16,
+            Self::Size16x8 => 8
+        }
+    }
+
+    // Returns the number of block rows and the packed block bitmap, MSB first.
+    // A block is left visible only if every pixel in it is set in the mask.
+    fn bitmap<D: AsRef<[u8]>>(&self, mask: &Bitplanes<D>) -> (usize, Vec<u8>) {
+        let (width, height) = (mask.width(), mask.height());
+        let block_height = self.block_height();
+        let cols = width.div_ceil(16);
+        let rows = height.div_ceil(block_height);
+        let row_bytes = cols.div_ceil(8);
+        let mut bitmap = vec![0u8; rows*row_bytes];
+
+        for by in 0..rows {
+            let ys = by*block_height..((by+1)*block_height).min(height);
+
+            for bx in 0..cols {
+                let xs = bx*16..((bx+1)*16).min(width);
+                let visible = ys.clone()
+                    .all(|y| xs.clone().all(|x| mask.get(0, x, y)));
+
+                if visible { bitmap[by*row_bytes+bx/8] |= 1 << (7-bx%8); }
+            }
+        }
+
+        (rows, bitmap)
+    }
+}
+
+
+
+// Offsets are in pixels
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct ProjectionTransform {
+    pub x_offset: isize,
+    pub y_offset: isize,
+    pub invert: bool,
+    pub upside_down: bool,
+    pub left_right_flip: bool
+}
+
+
+
+#[repr(i64)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum OffsetSelect {
+    Global = ALP_X_OFFSET_GLOBAL as i64,
+    Sequence = ALP_X_OFFSET_SEQ as i64
+}
+
+
+
+// Per-row horizontal shift, in pixels
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShearTable {
+    shifts: Vec<isize>
+}
+
+impl ShearTable {
+    pub fn from_shifts(shifts: Vec<isize>) -> Self {
+        Self { shifts }
+    }
+
+    pub fn from_angle(rows: usize, angle_rad: f64) -> Self {
+        let slope = angle_rad.tan();
+        let shifts = (0..rows)
+            .map(|y| (y as f64*slope).round() as isize)
+            .collect();
+
+        Self { shifts }
+    }
+
+    pub fn shifts(&self) -> &[isize] {
+        &self.shifts
+    }
+
+    pub fn len(&self) -> usize {
+        self.shifts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shifts.is_empty()
+    }
+}
+
+
+
+#[repr(i64)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Control {
     SeqRepeat = ALP_SEQ_REPEAT as i64,
     FirstFrame = ALP_FIRSTFRAME as i64,
@@ -270,5 +2004,194 @@ pub enum Control {
     FlutOffset9 = ALP_FLUT_OFFSET9 as i64,
     SeqLines = ALP_SEQ_DMD_LINES as i64,
     PwmMode = ALP_PWM_MODE as i64,
-    MaskSelect = ALP_DMD_MASK_SELECT as i64
+    MaskSelect = ALP_DMD_MASK_SELECT as i64,
+    SeqConfig = ALP_SEQ_CONFIG as i64,
+    BitplaneLutEntries = ALP_BITPLANE_LUT_ENTRIES as i64,
+    XShearSelect = ALP_X_SHEAR_SELECT as i64,
+    XOffsetSelect = ALP_X_OFFSET_SELECT as i64,
+    DynSyncOutPeriod = ALP_SEQ_DYN_SYNCH_OUT_PERIOD as i64,
+    DynSyncOutPulseWidth = ALP_SEQ_DYN_SYNCH_OUT_PULSEWIDTH as i64
+}
+
+
+
+#[repr(i64)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DeviceControl {
+    SyncPolarity = ALP_SYNCH_POLARITY as i64,
+    TriggerEdge = ALP_TRIGGER_EDGE as i64,
+    UsbDisconnectBehaviour = ALP_USB_DISCONNECT_BEHAVIOUR as i64,
+    Gpio5PinMux = ALP_DEV_GPIO5_PIN_MUX as i64,
+    PwmLevel = ALP_PWM_LEVEL as i64,
+    DmdMode = ALP_DEV_DMD_MODE as i64,
+    DynSyncOutWatchdog = ALP_DEV_DYN_SYNCH_OUT_WATCHDOG as i64
+}
+
+
+
+#[repr(i64)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ProjControl {
+    FlutWrite9Bit = ALP_FLUT_WRITE_9BIT as i64,
+    FlutWrite18Bit = ALP_FLUT_WRITE_18BIT as i64,
+    BplutWrite = ALP_BPLUT_WRITE as i64,
+    DmdMaskWrite = ALP_DMD_MASK_WRITE as i64,
+    DmdMaskWrite16K = ALP_DMD_MASK_WRITE_16K as i64,
+    XShear = ALP_X_SHEAR as i64,
+    XOffset = ALP_X_OFFSET as i64,
+    YOffset = ALP_Y_OFFSET as i64,
+    Inversion = ALP_PROJ_INVERSION as i64,
+    UpsideDown = ALP_PROJ_UPSIDE_DOWN as i64,
+    LeftRightFlip = ALP_PROJ_LEFT_RIGHT_FLIP as i64,
+    QueueMode = ALP_PROJ_QUEUE_MODE as i64,
+    ResetQueue = ALP_PROJ_RESET_QUEUE as i64,
+    AbortSequence = ALP_PROJ_ABORT_SEQUENCE as i64,
+    AbortFrame = ALP_PROJ_ABORT_FRAME as i64,
+    Mode = ALP_PROJ_MODE as i64,
+    Step = ALP_PROJ_STEP as i64,
+    WaitUntil = ALP_PROJ_WAIT_UNTIL as i64,
+    AbortAsync = ALP_PROJ_ABORT_ASYNC as i64
+}
+
+
+
+#[repr(i64)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LedControl {
+    SetCurrent = ALP_LED_SET_CURRENT as i64,
+    Brightness = ALP_LED_BRIGHTNESS as i64,
+    ForceOff = ALP_LED_FORCE_OFF as i64
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_range_bounds() {
+        assert_eq!(check_range("x", 5, 5, 10), Ok(()));
+        assert_eq!(check_range("x", 10, 5, 10), Ok(()));
+        assert_eq!(
+            check_range("x", 11, 5, 10),
+            Err(AlpError::OutOfRange { param: "x", value: 11, min: 5, max: 10 })
+        );
+        assert!(check_range("x", 4, 5, 10).is_err());
+    }
+
+    #[test]
+    fn make_long_packing() {
+        assert_eq!(make_long(0x1234, 0x0056), 0x0056_1234);
+        assert_eq!(make_long(0x1_0001, 2), 0x0002_0001);
+    }
+
+    #[test]
+    fn sync_gate_slots() {
+        let gate = SyncGate::new(4).slot(0, true).slot(3, true);
+
+        assert_eq!(gate.gate[..4], [true, false, false, true]);
+        assert_eq!(gate.bad_slot, None);
+
+        let gate = gate.slot(16, true).slot(20, true).slot(1, true);
+
+        assert_eq!(gate.bad_slot, Some(16));
+        assert!(gate.gate[1]);
+    }
+
+    #[test]
+    fn mask_bitmap_16x16() {
+        let mut mask = Bitplanes::new(1, 64, 32);
+
+        mask.fill(true);
+        mask.set(0, 17, 3, false);
+
+        let (rows, bitmap) = MaskBlocks::Size16x16.bitmap(&mask);
+
+        assert_eq!(rows, 2);
+        assert_eq!(bitmap, [0b1011_0000, 0b1111_0000]);
+    }
+
+    #[test]
+    fn mask_bitmap_16x8() {
+        let mut mask = Bitplanes::new(1, 32, 32);
+
+        mask.fill(true);
+        mask.set(0, 0, 8, false);
+        mask.set(0, 31, 31, false);
+
+        let (rows, bitmap) = MaskBlocks::Size16x8.bitmap(&mask);
+
+        assert_eq!(rows, 4);
+        assert_eq!(
+            bitmap,
+            [0b1100_0000, 0b0100_0000, 0b1100_0000, 0b1000_0000]
+        );
+    }
+
+    #[test]
+    fn mask_bitmap_partial_blocks() {
+        // 9 block columns span two bytes per row, the last block 4 pixels wide
+        let mut mask = Bitplanes::new(1, 132, 20);
+
+        mask.fill(true);
+
+        let (rows, bitmap) = MaskBlocks::Size16x16.bitmap(&mask);
+
+        assert_eq!(rows, 2);
+        assert_eq!(bitmap, [0xff, 0x80, 0xff, 0x80]);
+
+        mask.set(0, 131, 19, false);
+
+        let (_, bitmap) = MaskBlocks::Size16x16.bitmap(&mask);
+
+        assert_eq!(bitmap, [0xff, 0x80, 0xff, 0x00]);
+    }
+
+    #[test]
+    fn shear_table_from_angle() {
+        let table = ShearTable::from_angle(5, std::f64::consts::FRAC_PI_4);
+
+        assert_eq!(table.shifts(), [0, 1, 2, 3, 4]);
+
+        let table = ShearTable::from_angle(4, -0.3f64.atan());
+
+        assert_eq!(table.shifts(), [0, 0, -1, -1]);
+        assert_eq!(ShearTable::from_angle(3, 0.0).shifts(), [0, 0, 0]);
+    }
+
+    #[test]
+    fn dmd_type_specs() {
+        assert_eq!(DmdType::Xga.size(), Some((1024, 768)));
+        assert_eq!(DmdType::Wqxga480MHz090A.size(), Some((2560, 1600)));
+        assert_eq!(DmdType::Hd1080p095A.mirror_pitch_um(), Some(10.8));
+        assert_eq!(DmdType::WxgaS450.max_binary_rate_hz(), Some(12500));
+        assert_eq!(DmdType::Disconnected.size(), None);
+        assert_eq!(DmdType::Other(42).size(), None);
+    }
+
+    #[test]
+    fn dmd_type_from_repr() {
+        let code = ALP_DMDTYPE_1080P_065A as i64;
+
+        assert_eq!(DmdType::from_repr(code), Some(DmdType::Hd1080p065A));
+        assert_eq!(DmdType::from_repr(42), None);
+    }
+
+    #[test]
+    fn led_current_limits() {
+        let uv = LedType::Cbt90Uv;
+        let max = uv.max_current_ma();
+
+        assert_eq!(uv.check_current_ma(0), Ok(()));
+        assert_eq!(uv.check_current_ma(max), Ok(()));
+        assert_eq!(uv.check_current_ma(max+1), Err(AlpError::OutOfRange {
+            param: "led_current_ma",
+            value: max as i64+1,
+            min: 0,
+            max: max as i64
+        }));
+        // A current given in microamps by mistake is rejected
+        assert!(LedType::CMultiRgb.check_current_ma(10_000_000).is_err());
+    }
 }
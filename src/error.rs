@@ -1,4 +1,5 @@
 use crate::alp_binding::*;
+use crate::DmdType;
 use std::error::Error;
 use strum_macros::FromRepr;
 use std::fmt::{self, Formatter, Display};
@@ -28,12 +29,36 @@ pub enum AlpError {
     DriverVersion = ALP_DRIVER_VERSION as i64,
     SdramInitFail = ALP_SDRAM_INIT as i64,
     ConfigMismatch = ALP_CONFIG_MISMATCH as i64,
-    Unknown = ALP_ERROR_UNKNOWN as i64
+    Unknown = ALP_ERROR_UNKNOWN as i64,
+    #[strum(disabled)]
+    DmdDisconnected,
+    #[strum(disabled)]
+    DmdMismatch {
+        dmd_type: DmdType,
+        nominal: (usize, usize),
+        reported: (usize, usize)
+    },
+    #[strum(disabled)]
+    OutOfRange { param: &'static str, value: i64, min: i64, max: i64 },
+    #[strum(disabled)]
+    DeviceNotFound { serial: u64 }
 }
 
 impl Display for AlpError {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
-        write!(f, "{self:?}")
+        match self {
+            Self::OutOfRange { param, value, min, max } => write!(
+                f, "{param} = {value} is outside the range {min}..={max}"
+            ),
+            Self::DmdMismatch { dmd_type, nominal, reported } => write!(
+                f, "{dmd_type:?} DMD is nominally {}x{} but reports {}x{}",
+                nominal.0, nominal.1, reported.0, reported.1
+            ),
+            Self::DeviceNotFound { serial } => write!(
+                f, "no free ALP device with serial number {serial}"
+            ),
+            _ => write!(f, "{self:?}")
+        }
     }
 }
 
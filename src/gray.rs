@@ -0,0 +1,178 @@
+use std::ops::{Range, Deref, DerefMut, Index};
+
+
+
+pub struct GrayImages<D: AsRef<[u8]>> {
+    width: usize,
+    height: usize,
+    frames: usize,
+    data: D
+}
+
+impl<D: AsRef<[u8]>> GrayImages<D> {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    
+    pub fn height(&self) -> usize {
+        self.height
+    }
+    
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    fn calc_index(&self, frame: usize, x: usize, y: usize) -> usize {
+        (frame*self.height+y)*self.width+x
+    }
+
+    pub fn get(&self, frame: usize, x: usize, y: usize) -> u8 {
+        self.data.as_ref()[self.calc_index(frame, x, y)]
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        self.data.as_ref()
+    }
+
+    pub fn to_owned(&self) -> GrayImages<Vec<u8>> {
+        GrayImages {
+            width: self.width,
+            height: self.height,
+            frames: self.frames,
+            data: self.as_slice().to_vec()
+        }
+    }
+}
+
+impl<D: AsRef<[u8]> + AsMut<[u8]>> GrayImages<D> {
+    pub fn set(&mut self, frame: usize, x: usize, y: usize, val: u8) {
+        let idx = self.calc_index(frame, x, y);
+
+        self.data.as_mut()[idx] = val;
+    }
+
+    pub fn fill(&mut self, val: u8) {
+        self.data.as_mut().fill(val);
+    }
+
+    pub fn fill_from_fn<F>(&mut self, mut f: F)
+    where F: FnMut(usize, usize, usize) -> u8 {
+        let frame_len = self.width*self.height;
+        let data = self.data.as_mut();
+
+        for (i, frame) in data.chunks_mut(frame_len).enumerate() {
+            for (y, row) in frame.chunks_mut(self.width).enumerate() {
+                for (x, px) in row.iter_mut().enumerate() {
+                    *px = f(i, x, y);
+                }
+            }
+        }
+    }
+
+    pub fn frame(&mut self, n: usize) -> GrayImages<&mut [u8]> {
+        self.frame_range(n..n+1)
+    }
+
+    pub fn frame_range(&mut self, range: Range<usize>)
+    -> GrayImages<&mut [u8]> {
+        let frame_len = self.width*self.height;
+        let start = range.start*frame_len;
+        let end = range.end*frame_len;
+
+        GrayImages {
+            width: self.width,
+            height: self.height,
+            frames: range.end-range.start,
+            data: &mut self.data.as_mut()[start..end]
+        }
+    }
+
+    pub fn as_slice_mut(&mut self) -> &mut [u8] {
+        self.data.as_mut()
+    }
+}
+
+impl GrayImages<Vec<u8>> {
+    pub fn new(frames: usize, width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            frames,
+            data: vec![0; width*height*frames]
+        }
+    }
+
+    pub fn from_fn<F>(frames: usize, width: usize, height: usize, f: F) -> Self
+    where F: FnMut(usize, usize, usize) -> u8 {
+        let mut this = Self::new(frames, width, height);
+
+        this.fill_from_fn(f);
+        this
+    }
+}
+
+impl<D: AsRef<[u8]>> Deref for GrayImages<D> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.data.as_ref()
+    }
+}
+
+impl<D: AsRef<[u8]> + AsMut<[u8]>> DerefMut for GrayImages<D> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        self.data.as_mut()
+    }
+}
+
+impl<D: AsRef<[u8]>> Index<[usize; 3]> for GrayImages<D> {
+    type Output = u8;
+
+    fn index(&self, idx: [usize; 3]) -> &u8 {
+        &self.data.as_ref()[self.calc_index(idx[0], idx[1], idx[2])]
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layout_is_frame_row_major() {
+        let images = GrayImages::from_fn(2, 3, 2, |f, x, y| {
+            (f*100+y*10+x) as u8
+        });
+
+        assert_eq!(images.len(), 12);
+        assert_eq!(images.as_slice()[..6], [0, 1, 2, 10, 11, 12]);
+        assert_eq!(images.get(1, 2, 1), 112);
+        assert_eq!(images[[1, 0, 1]], 110);
+    }
+
+    #[test]
+    fn set_and_fill() {
+        let mut images = GrayImages::new(2, 4, 4);
+
+        images.fill(7);
+        images.set(1, 3, 2, 200);
+
+        assert_eq!(images.get(0, 3, 2), 7);
+        assert_eq!(images.get(1, 3, 2), 200);
+    }
+
+    #[test]
+    fn frame_views() {
+        let mut images = GrayImages::new(3, 2, 2);
+
+        images.frame(1).fill(5);
+
+        let view = images.frame_range(1..3);
+
+        assert_eq!(view.frames(), 2);
+        assert_eq!(view.get(0, 1, 1), 5);
+        assert_eq!(view.get(1, 1, 1), 0);
+        assert_eq!(images.get(0, 0, 0), 0);
+    }
+}
@@ -2,7 +2,18 @@ mod alp_binding;
 mod alp;
 mod error;
 mod bitplane;
+mod gray;
 
-pub use alp::{Alp, AlpDevice, AlpSequence, DataFormat};
+pub use alp::{
+    Alp, AlpDevice, AlpSequence, AlpQueue, AlpLed, LedType, DataFormat,
+    DeviceInfo, DeviceState, DmdType, Temperatures, ThermalWatchdog,
+    WatchdogAction, SyncPolarity, TriggerEdge, UsbDisconnect, Gpio5Mux,
+    SyncOutput, SyncGate, DmdMode, ParkGuard, AttachedDevice, SequenceInfo,
+    SequenceTiming, BinMode, PwmMode, ScrollConfig, FlutMode, FrameLut,
+    BitplaneLutMode, BitplaneLut, MaskBlocks, ShearTable,
+    ProjectionTransform, OffsetSelect, ProjectionProgress, ProjectionMode,
+    WaitUntil, AbortPoint, LedForce, GrayAlign
+};
 pub use error::{AlpResult, AlpError};
 pub use bitplane::Bitplanes;
+pub use gray::GrayImages;